@@ -3,10 +3,12 @@
  * Licensed under the MIT License. See LICENSE in the project root for license information.
  */
 use anyhow::{Context, Result};
-use cargo_metadata::{DependencyKind, Metadata, MetadataCommand, Node};
+use cargo_metadata::camino::Utf8PathBuf;
+use cargo_metadata::{DependencyKind, Metadata, MetadataCommand, Node, NodeDep};
 use clap::{arg, Parser};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::time::Duration;
 use std::{env, thread};
@@ -24,7 +26,7 @@ struct Args {
     #[arg(long)]
     workspace_only: bool,
 
-    /// Command to execute for each dependency. Use '{}', '{version}' and '{path}' to replace with the name, version and path of the dependency.
+    /// Command to execute for each dependency. Use '{}', '{version}', '{path}', '{features}', '{manifest}' and '{index}' to replace with the name, version, path, feature list, manifest path and topological position of the dependency. The same values are also exported as CARGO_DEP_* environment variables.
     #[arg(long, value_name = "COMMAND")]
     exec: Option<String>,
 
@@ -39,6 +41,50 @@ struct Args {
         help = "Specify the verbosity level for output"
     )]
     print: Option<PrintLevel>,
+
+    /// Output format for the dependency listing.
+    #[arg(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+
+    /// Run commands for independent dependencies concurrently, up to N at a time per topological level.
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    jobs: Option<u64>,
+
+    /// Stop dispatching further levels after the first command failure, letting in-flight jobs finish. Only applies with --jobs greater than 1; serial execution always runs every command.
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Report the latest and latest-compatible registry version for each non-local dependency instead of executing anything. Path and workspace crates are not listed.
+    #[arg(long)]
+    outdated: bool,
+
+    /// Skip the registry lookup that --outdated performs.
+    #[arg(long)]
+    offline: bool,
+
+    /// Target triple to evaluate cfg(...) predicates against. Defaults to the host triple.
+    #[arg(long, value_name = "TRIPLE")]
+    target: Option<String>,
+
+    /// Prune dependency edges whose cfg(...) predicate does not match --target.
+    #[arg(long)]
+    filter_platform: bool,
+
+    /// Dependency kinds to follow when building the dependency graph. May be given multiple times.
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [KindArg::Normal, KindArg::Build])]
+    kind: Vec<KindArg>,
+
+    /// Print the fully-substituted command and working directory for each dependency instead of running it.
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum KindArg {
+    Normal,
+    Build,
+    Dev,
+    All,
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -48,57 +94,77 @@ enum PrintLevel {
     Short,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
+#[derive(Serialize, Clone)]
 struct Dependency {
     name: String,
     version: String,
-    path: PathBuf,
+    path: Utf8PathBuf,
+    /// Full path to this dependency's Cargo.toml.
+    manifest: Utf8PathBuf,
+    /// Feature set resolved for this dependency.
+    features: Vec<String>,
+    /// Position of this dependency in the leaf-first topological order.
+    index: usize,
+    /// Package id used to look this dependency back up in the resolve graph.
+    #[serde(skip)]
+    id: String,
 }
 
-// Function to recursively visit dependencies and order them leaf-first
-fn visit_dep<'a>(
-    node: &'a Node,
-    dep_graph: &HashMap<&'a str, &'a Node>,
-    workspace_members: &HashSet<&'a str>,
-    visited: &mut HashSet<&'a str>,
-    output: &mut Vec<&'a str>,
-) {
-    if visited.contains(node.id.repr.as_str()) {
-        return;
-    }
-
-    if !workspace_members.contains(node.id.repr.as_str()) {
-        return;
-    }
-
-    visited.insert(node.id.repr.as_str());
-
-    // Visit all its dependencies (children)
-    for dep in &node.deps {
-        if let Some(dep_node) = dep_graph.get(dep.pkg.repr.as_str()) {
-            visit_dep(dep_node, dep_graph, workspace_members, visited, output);
-        }
-    }
+/// Expands the requested `--kind` values into the set of `DependencyKind`s a dependency edge
+/// must carry at least one of to be followed. `KindArg::All` expands to every kind.
+fn allowed_kinds(kinds: &[KindArg]) -> HashSet<DependencyKind> {
+    kinds
+        .iter()
+        .flat_map(|kind| match kind {
+            KindArg::Normal => vec![DependencyKind::Normal],
+            KindArg::Build => vec![DependencyKind::Build],
+            KindArg::Dev => vec![DependencyKind::Development],
+            KindArg::All => vec![
+                DependencyKind::Normal,
+                DependencyKind::Build,
+                DependencyKind::Development,
+            ],
+        })
+        .collect()
+}
 
-    output.push(node.id.repr.as_str());
+/// Returns whether `dep` carries at least one of `allowed_kinds` on any of its `dep_kinds`.
+fn edge_matches_kind(dep: &NodeDep, allowed_kinds: &HashSet<DependencyKind>) -> bool {
+    dep.dep_kinds.iter().any(|info| allowed_kinds.contains(&info.kind))
 }
 
-fn list_dependencies(metadata: &Metadata, workspace_only: bool) -> Vec<Dependency> {
-    // Gather all dev-dependency names
-    let dev_dependencies: HashSet<String> = metadata
-        .packages
-        .iter()
-        .flat_map(|pkg| {
-            pkg.dependencies.iter().filter_map(|dep| {
-                if dep.kind == DependencyKind::Development {
-                    Some(dep.name.clone()) // Collect the name of the dev-dependency
-                } else {
-                    None
-                }
-            })
-        })
-        .collect();
+/// Returns whether `dep` should be followed for `target_triple`: true when the edge has no
+/// platform restriction, or when at least one of its `dep_kinds` cfg(...) expressions evaluates
+/// to true for that triple. Errors evaluating a predicate are treated as a match, so an unknown
+/// or malformed cfg expression never silently hides a dependency.
+fn edge_matches_target(dep: &NodeDep, target_triple: &str) -> bool {
+    dep.dep_kinds.iter().any(|info| match &info.target {
+        None => true,
+        Some(platform) => target_spec::eval(&platform.to_string(), target_triple)
+            .unwrap_or(Some(true))
+            .unwrap_or(true),
+    })
+}
 
-    // Determine which packages to consider based on workspace_only
+/// Builds the package set and resolve-graph lookup shared by `list_dependencies` and
+/// `compute_levels`, plus the edge predicate (kind and, if filtering, target platform) both of
+/// them traverse the graph with.
+fn build_graph_context<'a>(
+    metadata: &'a Metadata,
+    workspace_only: bool,
+    target_triple: Option<&'a str>,
+    allowed_kinds: &'a HashSet<DependencyKind>,
+) -> (
+    HashSet<&'a str>,
+    HashMap<&'a str, &'a Node>,
+    impl Fn(&NodeDep) -> bool + 'a,
+) {
     let packages: HashSet<&str> = if workspace_only {
         metadata
             .workspace_members
@@ -118,12 +184,99 @@ fn list_dependencies(metadata: &Metadata, workspace_only: bool) -> Vec<Dependenc
         .as_ref()
         .expect("Failed to resolve dependencies");
 
-    let dep_graph: HashMap<_, _> = resolve
+    let dep_graph: HashMap<&str, &Node> = resolve
         .nodes
         .iter()
         .map(|node| (node.id.repr.as_str(), node))
         .collect();
 
+    let edge_allowed = move |dep: &NodeDep| -> bool {
+        edge_matches_kind(dep, allowed_kinds)
+            && target_triple.is_none_or(|triple| edge_matches_target(dep, triple))
+    };
+
+    (packages, dep_graph, edge_allowed)
+}
+
+/// Walks the resolve graph from the workspace's own packages, following only edges that
+/// `edge_allowed` permits, and returns the ids of every package still reachable. Used to exclude
+/// dependencies that are only pulled in via an excluded kind or an unmatched target platform.
+fn reachable_via<'a>(
+    metadata: &'a Metadata,
+    dep_graph: &HashMap<&'a str, &'a Node>,
+    edge_allowed: &dyn Fn(&NodeDep) -> bool,
+) -> HashSet<&'a str> {
+    let mut reachable = HashSet::new();
+    let mut stack: Vec<&str> = metadata
+        .workspace_members
+        .iter()
+        .map(|id| id.repr.as_str())
+        .collect();
+
+    while let Some(id) = stack.pop() {
+        if !reachable.insert(id) {
+            continue;
+        }
+
+        if let Some(node) = dep_graph.get(id) {
+            for dep in &node.deps {
+                if edge_allowed(dep) {
+                    stack.push(dep.pkg.repr.as_str());
+                }
+            }
+        }
+    }
+
+    reachable
+}
+
+/// Recursively visits dependencies and orders them leaf-first.
+fn visit_dep<'a>(
+    node: &'a Node,
+    dep_graph: &HashMap<&'a str, &'a Node>,
+    workspace_members: &HashSet<&'a str>,
+    visited: &mut HashSet<&'a str>,
+    output: &mut Vec<&'a str>,
+    edge_allowed: &dyn Fn(&NodeDep) -> bool,
+) {
+    if visited.contains(node.id.repr.as_str()) {
+        return;
+    }
+
+    if !workspace_members.contains(node.id.repr.as_str()) {
+        return;
+    }
+
+    visited.insert(node.id.repr.as_str());
+
+    // Visit all its dependencies (children)
+    for dep in &node.deps {
+        if !edge_allowed(dep) {
+            continue;
+        }
+
+        if let Some(dep_node) = dep_graph.get(dep.pkg.repr.as_str()) {
+            visit_dep(dep_node, dep_graph, workspace_members, visited, output, edge_allowed);
+        }
+    }
+
+    output.push(node.id.repr.as_str());
+}
+
+/// Returns the dependencies of the selected packages in leaf-first topological order.
+fn list_dependencies(
+    metadata: &Metadata,
+    workspace_only: bool,
+    target_triple: Option<&str>,
+    allowed_kinds: &HashSet<DependencyKind>,
+) -> Vec<Dependency> {
+    let (packages, dep_graph, edge_allowed) =
+        build_graph_context(metadata, workspace_only, target_triple, allowed_kinds);
+
+    // A dependency only belongs in the output if it's actually reachable from the workspace's
+    // own packages via edges of a selected kind (and, if filtering, a matching target platform).
+    let reachable = reachable_via(metadata, &dep_graph, &edge_allowed);
+
     let mut visited = HashSet::new();
     let mut output = Vec::new();
 
@@ -133,19 +286,27 @@ fn list_dependencies(metadata: &Metadata, workspace_only: bool) -> Vec<Dependenc
             continue;
         }
 
-        // Skip packages that are listed in dev-dependencies
-        if dev_dependencies.contains(&package.name) {
-            continue; // Exclude dev-dependencies
+        // Skip packages that aren't reachable via a selected dependency kind/platform
+        if !reachable.contains(package.id.repr.as_str()) {
+            continue;
         }
 
         if let Some(root_node) = dep_graph.get(package.id.repr.as_str()) {
-            visit_dep(root_node, &dep_graph, &packages, &mut visited, &mut output);
+            visit_dep(
+                root_node,
+                &dep_graph,
+                &packages,
+                &mut visited,
+                &mut output,
+                &edge_allowed,
+            );
         }
     }
 
     output
         .into_iter()
-        .filter_map(|pkg_id| {
+        .enumerate()
+        .filter_map(|(index, pkg_id)| {
             metadata
                 .packages
                 .iter()
@@ -153,21 +314,174 @@ fn list_dependencies(metadata: &Metadata, workspace_only: bool) -> Vec<Dependenc
                     pkg.id.repr == pkg_id
                         && (!workspace_only || packages.contains(pkg.id.repr.as_str()))
                 })
-                .map(|pkg| Dependency {
-                    name: pkg.name.clone(),
-                    version: pkg.version.to_string(),
-                    path: pkg.manifest_path.parent().unwrap().to_path_buf().into(),
+                .map(|pkg| {
+                    let features = dep_graph
+                        .get(pkg.id.repr.as_str())
+                        .map(|node| node.features.clone())
+                        .unwrap_or_default();
+
+                    Dependency {
+                        name: pkg.name.clone(),
+                        version: pkg.version.to_string(),
+                        path: pkg.manifest_path.parent().unwrap().to_path_buf(),
+                        manifest: pkg.manifest_path.clone(),
+                        features,
+                        index,
+                        id: pkg.id.repr.clone(),
+                    }
                 })
         })
         .collect()
 }
 
-fn execute_command(command_template: &str, dependency: &Dependency) -> Result<()> {
+/// Groups the selected packages into topological "levels" using Kahn's algorithm: level 0
+/// contains every dependency with no unprocessed dependency of its own, level 1 contains
+/// everything that only depends on level 0, and so on. Dependencies within the same level
+/// have no edges between them and can be executed concurrently.
+fn compute_levels<'a>(
+    metadata: &Metadata,
+    workspace_only: bool,
+    target_triple: Option<&str>,
+    allowed_kinds: &HashSet<DependencyKind>,
+    dependencies: &'a [Dependency],
+) -> Vec<Vec<&'a Dependency>> {
+    let (packages, dep_graph, edge_allowed) =
+        build_graph_context(metadata, workspace_only, target_triple, allowed_kinds);
+
+    // in_degree[id] counts how many of id's own dependencies (within the selected
+    // package set) have not been processed yet; successors[id] lists the packages
+    // that depend on id, so they can be decremented once id is done.
+    let mut in_degree: HashMap<&str, usize> = packages.iter().map(|&id| (id, 0)).collect();
+    let mut successors: HashMap<&str, Vec<&str>> = HashMap::new();
+
+    for &id in &packages {
+        if let Some(node) = dep_graph.get(id) {
+            for dep in &node.deps {
+                let dep_id = dep.pkg.repr.as_str();
+                if packages.contains(dep_id) && edge_allowed(dep) {
+                    *in_degree.get_mut(id).unwrap() += 1;
+                    successors.entry(dep_id).or_default().push(id);
+                }
+            }
+        }
+    }
+
+    let by_id: HashMap<&str, &Dependency> = dependencies
+        .iter()
+        .map(|dep| (dep.id.as_str(), dep))
+        .collect();
+
+    let mut levels = Vec::new();
+    let mut remaining = in_degree;
+
+    while !remaining.is_empty() {
+        let ready: Vec<&str> = remaining
+            .iter()
+            .filter(|(_, &degree)| degree == 0)
+            .map(|(&id, _)| id)
+            .collect();
+
+        if ready.is_empty() {
+            // A cycle in the resolve graph should be impossible, but don't spin forever.
+            break;
+        }
+
+        for &id in &ready {
+            remaining.remove(id);
+            if let Some(successor_ids) = successors.get(id) {
+                for &successor in successor_ids {
+                    if let Some(degree) = remaining.get_mut(successor) {
+                        *degree -= 1;
+                    }
+                }
+            }
+        }
+
+        let level = ready
+            .into_iter()
+            .filter_map(|id| by_id.get(id).copied())
+            .collect();
+        levels.push(level);
+    }
+
+    levels
+}
+
+/// Runs `command` for every dependency, dispatching whole topological levels (as produced by
+/// [`compute_levels`]) with up to `jobs` commands in flight at a time. `--wait` is honored
+/// between levels rather than between individual dependencies. With `fail_fast`, dispatch of
+/// further levels stops after the first failure, but jobs already running are allowed to finish.
+fn execute_levels_in_parallel(
+    levels: &[Vec<&Dependency>],
+    command: &str,
+    jobs: usize,
+    fail_fast: bool,
+    wait_seconds: Option<u64>,
+    dry_run: bool,
+) -> Result<()> {
+    let mut errors = Vec::new();
+
+    'levels: for level in levels {
+        for chunk in level.chunks(jobs) {
+            let handles: Vec<_> = chunk
+                .iter()
+                .map(|dep| {
+                    let dep = (*dep).clone();
+                    let command = command.to_string();
+                    thread::spawn(move || {
+                        execute_command(&command, &dep, dry_run).map_err(|e| e.to_string())
+                    })
+                })
+                .collect();
+
+            for handle in handles {
+                if let Err(e) = handle.join().expect("dependency command thread panicked") {
+                    eprintln!("Error executing command: {e}");
+                    errors.push(e);
+                }
+            }
+        }
+
+        if !errors.is_empty() && fail_fast {
+            break 'levels;
+        }
+
+        if !dry_run {
+            if let Some(seconds) = wait_seconds {
+                if seconds > 0 {
+                    println!("Waiting for {seconds} seconds before next level...");
+                    thread::sleep(Duration::from_secs(seconds));
+                }
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!("{} dependency command(s) failed", errors.len());
+    }
+
+    Ok(())
+}
+
+/// Expands `command_template`'s placeholders for `dependency` and runs the result through the
+/// platform shell in the dependency's directory. With `dry_run`, the expanded command is printed
+/// instead of run.
+fn execute_command(command_template: &str, dependency: &Dependency, dry_run: bool) -> Result<()> {
+    let features = dependency.features.join(",");
+
     // Replace additional placeholders as needed
     let command_str = command_template
         .replace("{}", &dependency.name)
         .replace("{version}", &dependency.version)
-        .replace("{path}", dependency.path.to_str().unwrap_or(""));
+        .replace("{path}", dependency.path.as_str())
+        .replace("{features}", &features)
+        .replace("{manifest}", dependency.manifest.as_str())
+        .replace("{index}", &dependency.index.to_string());
+
+    if dry_run {
+        println!("[dry-run] (cwd: {}) {command_str}", dependency.path);
+        return Ok(());
+    }
 
     // Determine the shell based on the OS. TODO: Find a cleaner way to do this
     #[cfg(target_family = "unix")]
@@ -184,6 +498,12 @@ fn execute_command(command_template: &str, dependency: &Dependency) -> Result<()
         .arg(shell_arg)
         .arg(&command_str)
         .current_dir(&dependency.path)
+        .env("CARGO_DEP_NAME", &dependency.name)
+        .env("CARGO_DEP_VERSION", &dependency.version)
+        .env("CARGO_DEP_PATH", dependency.path.as_str())
+        .env("CARGO_DEP_FEATURES", &features)
+        .env("CARGO_DEP_MANIFEST", dependency.manifest.as_str())
+        .env("CARGO_DEP_INDEX", dependency.index.to_string())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
@@ -196,6 +516,142 @@ fn execute_command(command_template: &str, dependency: &Dependency) -> Result<()
     Ok(())
 }
 
+#[derive(Deserialize)]
+struct RegistryResponse {
+    versions: Vec<RegistryVersion>,
+}
+
+#[derive(Deserialize)]
+struct RegistryVersion {
+    num: String,
+    yanked: bool,
+}
+
+/// Queries the crates.io index for every published, non-yanked version of `crate_name`.
+fn fetch_registry_versions(crate_name: &str) -> Result<Vec<Version>> {
+    let url = format!("https://crates.io/api/v1/crates/{crate_name}");
+
+    let response: RegistryResponse = ureq::get(&url)
+        .set(
+            "User-Agent",
+            "cargo-deps-order (https://github.com/piot/cargo-deps-list)",
+        )
+        .call()
+        .with_context(|| format!("Failed to query crates.io for '{crate_name}'"))?
+        .into_json()
+        .with_context(|| format!("Failed to parse crates.io response for '{crate_name}'"))?;
+
+    Ok(response
+        .versions
+        .into_iter()
+        .filter(|version| !version.yanked)
+        .filter_map(|version| Version::parse(&version.num).ok())
+        .collect())
+}
+
+struct OutdatedRow {
+    name: String,
+    current: Version,
+    compat: Option<Version>,
+    latest: Option<Version>,
+}
+
+/// Looks up the latest published version of `dep` and the latest version that is still
+/// semver-compatible with the one currently resolved.
+fn check_outdated(dep: &Dependency) -> Result<OutdatedRow> {
+    let current = Version::parse(&dep.version)
+        .with_context(|| format!("'{}' has an unparsable version '{}'", dep.name, dep.version))?;
+
+    let mut versions = fetch_registry_versions(&dep.name)?;
+    versions.sort();
+
+    let latest = versions.last().cloned();
+
+    let compat_req = VersionReq::parse(&format!("^{current}")).ok();
+    let compat = compat_req.and_then(|req| versions.iter().filter(|v| req.matches(v)).max().cloned());
+
+    Ok(OutdatedRow {
+        name: dep.name.clone(),
+        current,
+        compat,
+        latest,
+    })
+}
+
+/// Prints a `name current compat latest` report for every non-local dependency, flagging rows
+/// where a newer version than the one currently resolved is available. With `offline`, the
+/// registry is never queried: only `name`/`current` are filled in, and `compat`/`latest` show `-`.
+fn print_outdated(metadata: &Metadata, dependencies: &[Dependency], offline: bool) -> Result<()> {
+    // Packages with no registry source (workspace members, but also any other path dependency
+    // that was never added to `workspace.members`) have nothing on crates.io to compare against.
+    let local_names: HashSet<&str> = metadata
+        .packages
+        .iter()
+        .filter(|pkg| pkg.source.is_none())
+        .map(|pkg| pkg.name.as_str())
+        .collect();
+
+    println!(
+        "{:<1}{:<30} {:<15} {:<15} {:<15}",
+        "", "name", "current", "compat", "latest"
+    );
+
+    for dep in dependencies {
+        // Path/workspace crates have no crates.io entry to compare against.
+        if local_names.contains(dep.name.as_str()) {
+            continue;
+        }
+
+        if offline {
+            // No registry access: print what's known locally and leave compat/latest blank.
+            let current = Version::parse(&dep.version)
+                .with_context(|| format!("'{}' has an unparsable version '{}'", dep.name, dep.version))?;
+            println!("{:<1}{:<30} {:<15} {:<15} {:<15}", "", dep.name, current, "-", "-");
+            continue;
+        }
+
+        match check_outdated(dep) {
+            Ok(row) => {
+                let is_outdated = row
+                    .latest
+                    .as_ref()
+                    .map(|latest| *latest > row.current)
+                    .unwrap_or(false);
+
+                println!(
+                    "{:<1}{:<30} {:<15} {:<15} {:<15}",
+                    if is_outdated { "*" } else { "" },
+                    row.name,
+                    row.current,
+                    row.compat
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                    row.latest
+                        .map(|v| v.to_string())
+                        .unwrap_or_else(|| "-".to_string()),
+                );
+            }
+            Err(e) => eprintln!("Error checking '{}': {}", dep.name, e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Determines the host target triple by asking `rustc`, used as the default for `--target`.
+fn host_triple() -> Result<String> {
+    let output = Command::new("rustc")
+        .arg("-vV")
+        .output()
+        .context("Failed to run `rustc -vV` to determine the host triple")?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(|triple| triple.to_string())
+        .context("Could not find a `host:` line in `rustc -vV` output")
+}
+
 fn main() -> Result<()> {
     // Collect arguments from the command line
     let mut raw_args: Vec<String> = env::args().collect();
@@ -211,36 +667,226 @@ fn main() -> Result<()> {
     let exec_command = args.exec;
     let wait_seconds = args.wait;
     let print = args.print;
+    let format = args.format;
+    let jobs = args.jobs.unwrap_or(1) as usize;
+    let fail_fast = args.fail_fast;
+    let outdated = args.outdated;
+    let offline = args.offline;
+    let filter_platform = args.filter_platform;
+    let kinds = allowed_kinds(&args.kind);
+    let dry_run = args.dry_run;
 
     let metadata = MetadataCommand::new()
         .exec()
         .context("Failed to retrieve cargo metadata")?;
 
-    let dependencies = list_dependencies(&metadata, workspace_only);
+    let target_triple = if filter_platform {
+        Some(match args.target {
+            Some(triple) => triple,
+            None => host_triple()?,
+        })
+    } else {
+        None
+    };
+
+    let dependencies = list_dependencies(
+        &metadata,
+        workspace_only,
+        target_triple.as_deref(),
+        &kinds,
+    );
+
+    if outdated {
+        if offline {
+            println!("--offline given: skipping the registry check, showing name/current only");
+        }
+
+        return print_outdated(&metadata, &dependencies, offline);
+    }
+
+    if format == OutputFormat::Json {
+        let json = serde_json::to_string_pretty(&dependencies)
+            .context("Failed to serialize dependencies to JSON")?;
+        println!("{json}");
+    }
 
     for dep in &dependencies {
-        if let Some(x) = &print {
-            match x {
-                PrintLevel::Verbose => todo!(),
-                PrintLevel::Normal => println!("{}", dep.name),
-                PrintLevel::Short => todo!(),
+        if format == OutputFormat::Text {
+            if let Some(x) = &print {
+                match x {
+                    PrintLevel::Verbose => println!(
+                        "{} {} {} (#{})",
+                        dep.name, dep.version, dep.path, dep.index
+                    ),
+                    PrintLevel::Normal => println!("{} {}", dep.name, dep.version),
+                    PrintLevel::Short => println!("{}", dep.name),
+                }
             }
         }
 
-        if let Some(ref command) = exec_command {
-            if let Err(e) = execute_command(&command, dep) {
-                eprintln!("Error executing command for '{}': {}", dep.name, e);
+        if jobs <= 1 {
+            if let Some(ref command) = exec_command {
+                if let Err(e) = execute_command(command, dep, dry_run) {
+                    eprintln!("Error executing command for '{}': {}", dep.name, e);
+                }
             }
-        }
 
-        // If wait_seconds is specified and greater than 0, sleep for the given duration
-        if let Some(seconds) = wait_seconds {
-            if seconds > 0 {
-                println!("Waiting for {seconds} seconds before next command...");
-                thread::sleep(Duration::from_secs(seconds));
+            // If wait_seconds is specified and greater than 0, sleep for the given duration.
+            // Skipped under --dry-run, which previews a batch operation without committing to
+            // its side effects, including the wall-clock delay between commands.
+            if !dry_run {
+                if let Some(seconds) = wait_seconds {
+                    if seconds > 0 {
+                        println!("Waiting for {seconds} seconds before next command...");
+                        thread::sleep(Duration::from_secs(seconds));
+                    }
+                }
             }
         }
     }
 
+    if jobs > 1 {
+        if let Some(ref command) = exec_command {
+            let levels = compute_levels(
+                &metadata,
+                workspace_only,
+                target_triple.as_deref(),
+                &kinds,
+                &dependencies,
+            );
+            execute_levels_in_parallel(&levels, command, jobs, fail_fast, wait_seconds, dry_run)?;
+        }
+    }
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn allowed_kinds_expands_each_arg_to_its_own_kind() {
+        assert_eq!(
+            allowed_kinds(&[KindArg::Normal]),
+            HashSet::from([DependencyKind::Normal])
+        );
+        assert_eq!(
+            allowed_kinds(&[KindArg::Build]),
+            HashSet::from([DependencyKind::Build])
+        );
+        assert_eq!(
+            allowed_kinds(&[KindArg::Dev]),
+            HashSet::from([DependencyKind::Development])
+        );
+    }
+
+    #[test]
+    fn allowed_kinds_all_expands_to_every_kind() {
+        assert_eq!(
+            allowed_kinds(&[KindArg::All]),
+            HashSet::from([
+                DependencyKind::Normal,
+                DependencyKind::Build,
+                DependencyKind::Development,
+            ])
+        );
+    }
+
+    /// Mirrors the JSON schema `cargo metadata` emits, with just enough fields populated to
+    /// exercise `compute_levels`: a chain `app -> liba -> libb`, all normal dependencies.
+    fn chain_metadata() -> Metadata {
+        let json = r#"{
+            "packages": [
+                {
+                    "name": "app", "version": "0.1.0",
+                    "id": "app 0.1.0 (path+file:///workspace/app)",
+                    "license": null, "license_file": null, "description": null, "source": null,
+                    "dependencies": [], "targets": [], "features": {},
+                    "manifest_path": "/workspace/app/Cargo.toml",
+                    "categories": [], "keywords": [], "readme": null, "repository": null,
+                    "homepage": null, "documentation": null, "edition": "2021",
+                    "metadata": null, "links": null, "publish": null, "default_run": null,
+                    "rust_version": null
+                },
+                {
+                    "name": "liba", "version": "0.1.0",
+                    "id": "liba 0.1.0 (path+file:///workspace/liba)",
+                    "license": null, "license_file": null, "description": null, "source": null,
+                    "dependencies": [], "targets": [], "features": {},
+                    "manifest_path": "/workspace/liba/Cargo.toml",
+                    "categories": [], "keywords": [], "readme": null, "repository": null,
+                    "homepage": null, "documentation": null, "edition": "2021",
+                    "metadata": null, "links": null, "publish": null, "default_run": null,
+                    "rust_version": null
+                },
+                {
+                    "name": "libb", "version": "0.1.0",
+                    "id": "libb 0.1.0 (path+file:///workspace/libb)",
+                    "license": null, "license_file": null, "description": null, "source": null,
+                    "dependencies": [], "targets": [], "features": {},
+                    "manifest_path": "/workspace/libb/Cargo.toml",
+                    "categories": [], "keywords": [], "readme": null, "repository": null,
+                    "homepage": null, "documentation": null, "edition": "2021",
+                    "metadata": null, "links": null, "publish": null, "default_run": null,
+                    "rust_version": null
+                }
+            ],
+            "workspace_members": ["app 0.1.0 (path+file:///workspace/app)"],
+            "resolve": {
+                "nodes": [
+                    {
+                        "id": "app 0.1.0 (path+file:///workspace/app)",
+                        "dependencies": ["liba 0.1.0 (path+file:///workspace/liba)"],
+                        "deps": [{
+                            "name": "liba",
+                            "pkg": "liba 0.1.0 (path+file:///workspace/liba)",
+                            "dep_kinds": [{"kind": null, "target": null}]
+                        }],
+                        "features": []
+                    },
+                    {
+                        "id": "liba 0.1.0 (path+file:///workspace/liba)",
+                        "dependencies": ["libb 0.1.0 (path+file:///workspace/libb)"],
+                        "deps": [{
+                            "name": "libb",
+                            "pkg": "libb 0.1.0 (path+file:///workspace/libb)",
+                            "dep_kinds": [{"kind": null, "target": null}]
+                        }],
+                        "features": []
+                    },
+                    {
+                        "id": "libb 0.1.0 (path+file:///workspace/libb)",
+                        "dependencies": [],
+                        "deps": [],
+                        "features": []
+                    }
+                ],
+                "root": null
+            },
+            "target_directory": "/workspace/target",
+            "workspace_root": "/workspace",
+            "version": 1
+        }"#;
+
+        serde_json::from_str(json).expect("fixture must match the cargo-metadata schema")
+    }
+
+    #[test]
+    fn compute_levels_orders_chain_leaf_first() {
+        let metadata = chain_metadata();
+        let kinds = allowed_kinds(&[KindArg::All]);
+        let dependencies = list_dependencies(&metadata, false, None, &kinds);
+
+        let levels = compute_levels(&metadata, false, None, &kinds, &dependencies);
+        let level_names: Vec<Vec<&str>> = levels
+            .iter()
+            .map(|level| level.iter().map(|dep| dep.name.as_str()).collect())
+            .collect();
+
+        assert_eq!(
+            level_names,
+            vec![vec!["libb"], vec!["liba"], vec!["app"]]
+        );
+    }
+}